@@ -0,0 +1,239 @@
+use hashbrown::HashMap;
+use rethnet_eth::{Address, H256, U256};
+use revm::{AccountInfo, Bytecode};
+
+/// Default number of storage slots cached per account.
+pub const DEFAULT_STORAGE_CACHE_LIMIT_PER_ACCOUNT: usize = 8192;
+
+/// Default number of accounts cached globally.
+pub const DEFAULT_ACCOUNT_CACHE_LIMIT: usize = 8192;
+
+/// Default number of distinct contract codes cached globally.
+pub const DEFAULT_CODE_CACHE_LIMIT: usize = 1024;
+
+/// Configurable bounds for the [`LayerCache`] fronting a [`super::LayeredDatabase`].
+#[derive(Debug, Clone, Copy)]
+pub struct CacheLimits {
+    /// Maximum number of accounts cached at once.
+    pub account_limit: usize,
+    /// Maximum number of storage slots cached per account.
+    pub storage_limit_per_account: usize,
+    /// Maximum number of distinct contract codes cached at once.
+    pub code_limit: usize,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            account_limit: DEFAULT_ACCOUNT_CACHE_LIMIT,
+            storage_limit_per_account: DEFAULT_STORAGE_CACHE_LIMIT_PER_ACCOUNT,
+            code_limit: DEFAULT_CODE_CACHE_LIMIT,
+        }
+    }
+}
+
+/// A single bounded, least-recently-used map. Each entry also records the id of the stack layer
+/// it was populated from, so that a revert can cheaply invalidate everything that originated
+/// above the layer being discarded.
+#[derive(Debug)]
+struct Lru<K, V> {
+    limit: usize,
+    clock: u64,
+    entries: HashMap<K, (V, usize, u64)>,
+}
+
+impl<K: Eq + std::hash::Hash + Clone, V> Lru<K, V> {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|(value, _, last_used)| {
+            *last_used = clock;
+            &*value
+        })
+    }
+
+    /// Inserts `key` -> `value`, evicting the least-recently-used entry if the map is already at
+    /// its limit. Returns the evicted key, if any, so callers can clean up state that's keyed
+    /// off it (e.g. an account's storage slots when the account itself is evicted).
+    fn put(&mut self, key: K, value: V, layer_id: usize) -> Option<K> {
+        self.clock += 1;
+        let clock = self.clock;
+
+        let evicted = if !self.entries.contains_key(&key) && self.entries.len() >= self.limit {
+            let lru_key = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, _, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+
+            if let Some(lru_key) = &lru_key {
+                self.entries.remove(lru_key);
+            }
+            lru_key
+        } else {
+            None
+        };
+
+        self.entries.insert(key, (value, layer_id, clock));
+        evicted
+    }
+
+    fn invalidate(&mut self, key: &K) {
+        self.entries.remove(key);
+    }
+
+    /// Discards every entry that was populated from a layer above `layer_id`.
+    fn invalidate_above(&mut self, layer_id: usize) {
+        self.entries.retain(|_, (_, entry_layer_id, _)| *entry_layer_id <= layer_id);
+    }
+}
+
+/// A bounded LRU cache sitting in front of a layered database's `basic`/`storage`/`code_by_hash`
+/// hot paths, so repeated lookups of the same hot accounts/slots don't have to walk the full
+/// layer stack. The layer stack remains the source of truth; this cache only ever mirrors it.
+#[derive(Debug)]
+pub struct LayerCache {
+    limits: CacheLimits,
+    accounts: Lru<Address, AccountInfo>,
+    storage: HashMap<Address, Lru<U256, U256>>,
+    code: Lru<H256, Bytecode>,
+}
+
+impl LayerCache {
+    pub fn new(limits: CacheLimits) -> Self {
+        Self {
+            limits,
+            accounts: Lru::new(limits.account_limit),
+            storage: HashMap::new(),
+            code: Lru::new(limits.code_limit),
+        }
+    }
+
+    pub fn get_account(&mut self, address: &Address) -> Option<AccountInfo> {
+        self.accounts.get(address).cloned()
+    }
+
+    /// Caches `account_info`, evicting the least-recently-used account (and its storage slots)
+    /// if the account cap has been reached.
+    pub fn put_account(&mut self, address: Address, account_info: AccountInfo, layer_id: usize) {
+        if let Some(evicted) = self.accounts.put(address, account_info, layer_id) {
+            self.storage.remove(&evicted);
+        }
+    }
+
+    pub fn invalidate_account(&mut self, address: &Address) {
+        self.accounts.invalidate(address);
+        self.storage.remove(address);
+    }
+
+    pub fn get_storage(&mut self, address: &Address, index: &U256) -> Option<U256> {
+        self.storage.get_mut(address)?.get(index).copied()
+    }
+
+    pub fn put_storage(&mut self, address: Address, index: U256, value: U256, layer_id: usize) {
+        let limit = self.limits.storage_limit_per_account;
+        self.storage
+            .entry(address)
+            .or_insert_with(|| Lru::new(limit))
+            .put(index, value, layer_id);
+    }
+
+    pub fn invalidate_storage(&mut self, address: &Address, index: &U256) {
+        if let Some(slots) = self.storage.get_mut(address) {
+            slots.invalidate(index);
+        }
+    }
+
+    pub fn get_code(&mut self, code_hash: &H256) -> Option<Bytecode> {
+        self.code.get(code_hash).cloned()
+    }
+
+    /// Caches `code`, evicting the least-recently-used entry if the global code cap has been
+    /// reached.
+    pub fn put_code(&mut self, code_hash: H256, code: Bytecode, layer_id: usize) {
+        self.code.put(code_hash, code, layer_id);
+    }
+
+    /// Drops every cache entry that was populated from a layer above `layer_id`. Called when
+    /// `revert_to_layer` truncates the stack, so the cache can't keep serving stale writes made
+    /// by the discarded layers.
+    pub fn invalidate_above(&mut self, layer_id: usize) {
+        self.accounts.invalidate_above(layer_id);
+        self.storage
+            .values_mut()
+            .for_each(|slots| slots.invalidate_above(layer_id));
+        self.code.invalidate_above(layer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> Address {
+        Address::from([byte; 20])
+    }
+
+    fn limits(account_limit: usize, code_limit: usize) -> CacheLimits {
+        CacheLimits {
+            account_limit,
+            storage_limit_per_account: DEFAULT_STORAGE_CACHE_LIMIT_PER_ACCOUNT,
+            code_limit,
+        }
+    }
+
+    #[test]
+    fn account_cache_is_bounded_and_evicts_least_recently_used() {
+        let mut cache = LayerCache::new(limits(2, DEFAULT_CODE_CACHE_LIMIT));
+
+        cache.put_account(address(1), AccountInfo::default(), 0);
+        cache.put_account(address(2), AccountInfo::default(), 0);
+        // Touch account 1 so account 2 becomes the least-recently-used entry.
+        cache.get_account(&address(1));
+        cache.put_account(address(3), AccountInfo::default(), 0);
+
+        assert!(cache.get_account(&address(1)).is_some());
+        assert!(cache.get_account(&address(2)).is_none());
+        assert!(cache.get_account(&address(3)).is_some());
+    }
+
+    #[test]
+    fn evicting_an_account_also_evicts_its_storage() {
+        let mut cache = LayerCache::new(limits(1, DEFAULT_CODE_CACHE_LIMIT));
+
+        cache.put_account(address(1), AccountInfo::default(), 0);
+        cache.put_storage(address(1), U256::from(1), U256::from(42), 0);
+        assert_eq!(cache.get_storage(&address(1), &U256::from(1)), Some(U256::from(42)));
+
+        // Evicts account 1 (and, per the account cap, its storage slots with it).
+        cache.put_account(address(2), AccountInfo::default(), 0);
+
+        assert_eq!(cache.get_storage(&address(1), &U256::from(1)), None);
+    }
+
+    #[test]
+    fn code_cache_is_bounded_and_evicts_least_recently_used() {
+        let mut cache = LayerCache::new(limits(DEFAULT_ACCOUNT_CACHE_LIMIT, 2));
+
+        let hash_a = H256::from([1; 32]);
+        let hash_b = H256::from([2; 32]);
+        let hash_c = H256::from([3; 32]);
+
+        cache.put_code(hash_a, Bytecode::new(), 0);
+        cache.put_code(hash_b, Bytecode::new(), 0);
+        cache.get_code(&hash_a);
+        cache.put_code(hash_c, Bytecode::new(), 0);
+
+        assert!(cache.get_code(&hash_a).is_some());
+        assert!(cache.get_code(&hash_b).is_none());
+        assert!(cache.get_code(&hash_c).is_some());
+    }
+}
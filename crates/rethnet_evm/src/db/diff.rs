@@ -0,0 +1,52 @@
+use hashbrown::HashMap;
+use rethnet_eth::{Address, Bytes, U256};
+
+/// How a single field changed between two points in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diff<T> {
+    /// The field is unchanged.
+    Same,
+    /// The field didn't exist before and now does.
+    Born(T),
+    /// The field changed from one value to another.
+    Changed(T, T),
+    /// The field existed before and no longer does.
+    Died(T),
+}
+
+impl<T: PartialEq> Diff<T> {
+    pub(super) fn new(from: Option<T>, to: Option<T>) -> Self {
+        match (from, to) {
+            (None, None) => Diff::Same,
+            (None, Some(to)) => Diff::Born(to),
+            (Some(from), None) => Diff::Died(from),
+            (Some(from), Some(to)) if from == to => Diff::Same,
+            (Some(from), Some(to)) => Diff::Changed(from, to),
+        }
+    }
+
+    pub fn is_same(&self) -> bool {
+        matches!(self, Diff::Same)
+    }
+}
+
+/// The set of field-level changes an account underwent between two layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub balance: Diff<U256>,
+    pub nonce: Diff<u64>,
+    pub code: Diff<Bytes>,
+    pub storage: HashMap<U256, Diff<U256>>,
+}
+
+impl AccountDiff {
+    pub(super) fn is_unchanged(&self) -> bool {
+        self.balance.is_same() && self.nonce.is_same() && self.code.is_same() && self.storage.is_empty()
+    }
+}
+
+/// Per-address account diffs between two layers, omitting accounts that didn't change.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    pub accounts: HashMap<Address, AccountDiff>,
+}
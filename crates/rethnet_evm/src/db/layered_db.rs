@@ -1,20 +1,41 @@
 use anyhow::anyhow;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use rethnet_eth::{Address, Bytes, H256, U256};
 use revm::{Account, AccountInfo, Bytecode, Database, DatabaseCommit, KECCAK_EMPTY};
+use rlp::RlpStream;
 
-use crate::DatabaseDebug;
+use crate::{DatabaseDebug, Prefetch};
+
+use super::cache::{CacheLimits, LayerCache};
+use super::diff::{AccountDiff, Diff, StateDiff};
+use super::trie::{self, Trie};
 
 /// A database consisting of layers.
 #[derive(Debug)]
 pub struct LayeredDatabase<Layer> {
     stack: Vec<Layer>,
+    cache: LayerCache,
+    transaction_root_layer_id: usize,
 }
 
 impl<Layer> LayeredDatabase<Layer> {
     /// Creates a [`LayeredDatabase`] with the provided layer at the bottom.
     pub fn with_layer(layer: Layer) -> Self {
-        Self { stack: vec![layer] }
+        Self {
+            stack: vec![layer],
+            cache: LayerCache::new(CacheLimits::default()),
+            transaction_root_layer_id: 0,
+        }
+    }
+
+    /// Creates a [`LayeredDatabase`] with the provided layer at the bottom and the given cache
+    /// bounds, so embedders can tune how much memory the hot-path cache is allowed to use.
+    pub fn with_layer_and_cache_limits(layer: Layer, limits: CacheLimits) -> Self {
+        Self {
+            stack: vec![layer],
+            cache: LayerCache::new(limits),
+            transaction_root_layer_id: 0,
+        }
     }
 
     /// Returns the index of the top layer.
@@ -41,12 +62,21 @@ impl<Layer> LayeredDatabase<Layer> {
     pub fn revert_to_layer(&mut self, layer_id: usize) {
         assert!(layer_id < self.stack.len(), "Invalid layer id.");
         self.stack.truncate(layer_id + 1);
+        self.cache.invalidate_above(layer_id);
+        self.transaction_root_layer_id = self.transaction_root_layer_id.min(layer_id);
     }
 
     /// Returns an iterator over the object's layers.
     pub fn iter(&self) -> impl Iterator<Item = &Layer> {
         self.stack.iter().rev()
     }
+
+    /// Marks the current top layer as the root of a new transaction, i.e. the boundary that
+    /// [`LayeredDatabase::original_storage`] reads back to. Call this once per transaction,
+    /// before any of its (possibly nested, via `checkpoint`/`revert`) state changes are applied.
+    pub fn start_transaction(&mut self) {
+        self.transaction_root_layer_id = self.last_layer_id();
+    }
 }
 
 impl<Layer: Default> LayeredDatabase<Layer> {
@@ -61,6 +91,8 @@ impl<Layer: Default> Default for LayeredDatabase<Layer> {
     fn default() -> Self {
         Self {
             stack: vec![Layer::default()],
+            cache: LayerCache::new(CacheLimits::default()),
+            transaction_root_layer_id: 0,
         }
     }
 }
@@ -105,6 +137,12 @@ impl RethnetLayer {
 }
 
 impl LayeredDatabase<RethnetLayer> {
+    /// Creates an empty [`LayeredDatabase`] whose hot-path cache is bounded by `limits`, instead
+    /// of the default [`CacheLimits`].
+    pub fn with_cache_limits(limits: CacheLimits) -> Self {
+        Self::with_layer_and_cache_limits(RethnetLayer::default(), limits)
+    }
+
     /// Retrieves a reference to the account corresponding to the address, if it exists.
     pub fn account(&self, address: &Address) -> Option<&AccountInfo> {
         self.iter()
@@ -155,50 +193,300 @@ impl LayeredDatabase<RethnetLayer> {
             .insert_unique_unchecked(address.clone(), account_info)
             .1
     }
+
+    /// Inserts a storage slot directly into the top layer, bypassing the EVM's
+    /// [`DatabaseCommit`] path. Used to memoize values fetched from outside the layer stack
+    /// (e.g. by [`super::remote::ForkedDatabase`]).
+    pub fn insert_storage_slot(&mut self, address: Address, index: U256, value: U256) {
+        let layer_id = self.last_layer_id();
+
+        self.last_layer_mut()
+            .storage
+            .entry(address)
+            .or_default()
+            .insert(index, value);
+
+        self.cache.put_storage(address, index, value, layer_id);
+    }
+
+    /// Returns the value of the given storage slot as of the most recently opened checkpoint,
+    /// i.e. ignoring any write made in the current top layer. This is the "checkpoint value"
+    /// used by EIP-2200/EIP-1283 net gas metering.
+    pub fn checkpoint_storage(&self, address: &Address, index: &U256) -> U256 {
+        self.stack
+            .iter()
+            .rev()
+            .skip(1)
+            .find_map(|layer| {
+                layer
+                    .storage
+                    .get(address)
+                    .and_then(|storage| storage.get(index))
+                    .copied()
+            })
+            .unwrap_or(U256::ZERO)
+    }
+
+    /// Returns the value of the given storage slot as of the start of the current transaction
+    /// (see [`LayeredDatabase::start_transaction`]), i.e. ignoring any write made by this
+    /// transaction's own (possibly nested) checkpoints. Falls back to zero if the slot was
+    /// never written at or below the transaction root, matching a freshly created account's
+    /// all-zero storage.
+    pub fn original_storage(&self, address: &Address, index: &U256) -> U256 {
+        self.stack[..=self.transaction_root_layer_id]
+            .iter()
+            .rev()
+            .find_map(|layer| {
+                layer
+                    .storage
+                    .get(address)
+                    .and_then(|storage| storage.get(index))
+                    .copied()
+            })
+            .unwrap_or(U256::ZERO)
+    }
+
+    /// Computes the storage root of the given account's merged (across all layers) storage.
+    fn account_storage_root(&self, address: &Address) -> H256 {
+        trie::storage_root(&self.storage_as_of(address, self.last_layer_id()))
+    }
+
+    /// Merges the given account's storage as it stood at (and below) `layer_id`.
+    fn storage_as_of(&self, address: &Address, layer_id: usize) -> HashMap<U256, U256> {
+        let layers = &self.stack[..=layer_id];
+
+        let indices: HashSet<U256> = layers
+            .iter()
+            .filter_map(|layer| layer.storage.get(address))
+            .flat_map(|storage| storage.keys().copied())
+            .collect();
+
+        indices
+            .into_iter()
+            .filter_map(|index| {
+                layers
+                    .iter()
+                    .rev()
+                    .find_map(|layer| {
+                        layer
+                            .storage
+                            .get(address)
+                            .and_then(|storage| storage.get(&index))
+                            .copied()
+                    })
+                    .map(|value| (index, value))
+            })
+            .collect()
+    }
+
+    /// Returns the account as it stood at (and below) `layer_id`, or `None` if it didn't exist
+    /// (including if it was explicitly removed, which is represented as a default `AccountInfo`).
+    fn account_as_of(&self, address: &Address, layer_id: usize) -> Option<AccountInfo> {
+        self.stack[..=layer_id]
+            .iter()
+            .rev()
+            .find_map(|layer| layer.account_infos.get(address).cloned())
+            .filter(|account_info| *account_info != AccountInfo::default())
+    }
+
+    /// Returns the bytecode for `code_hash` as it stood at (and below) `layer_id`.
+    fn code_as_of(&self, code_hash: H256, layer_id: usize) -> Option<Bytes> {
+        if code_hash == KECCAK_EMPTY {
+            return None;
+        }
+
+        self.stack[..=layer_id]
+            .iter()
+            .rev()
+            .find_map(|layer| layer.contracts.get(&code_hash).cloned())
+    }
+
+    /// Computes a structured diff of every account touched between `from_layer_id` and
+    /// `to_layer_id`, akin to a `PodState` export: for each address, the change in balance,
+    /// nonce, code, and storage slots. Accounts (and slots) that didn't change are omitted.
+    pub fn diff(&self, from_layer_id: usize, to_layer_id: usize) -> StateDiff {
+        let bound = &self.stack[..=from_layer_id.max(to_layer_id)];
+        let addresses: HashSet<Address> = bound
+            .iter()
+            .flat_map(|layer| layer.account_infos.keys().copied())
+            .collect();
+
+        let mut accounts = HashMap::new();
+
+        for address in addresses {
+            let from_account = self.account_as_of(&address, from_layer_id);
+            let to_account = self.account_as_of(&address, to_layer_id);
+
+            if from_account.is_none() && to_account.is_none() {
+                continue;
+            }
+
+            let balance = Diff::new(
+                from_account.as_ref().map(|account| account.balance),
+                to_account.as_ref().map(|account| account.balance),
+            );
+            let nonce = Diff::new(
+                from_account.as_ref().map(|account| account.nonce),
+                to_account.as_ref().map(|account| account.nonce),
+            );
+            let code = Diff::new(
+                from_account
+                    .as_ref()
+                    .and_then(|account| self.code_as_of(account.code_hash, from_layer_id)),
+                to_account
+                    .as_ref()
+                    .and_then(|account| self.code_as_of(account.code_hash, to_layer_id)),
+            );
+
+            let from_storage = self.storage_as_of(&address, from_layer_id);
+            let to_storage = self.storage_as_of(&address, to_layer_id);
+
+            let indices: HashSet<U256> = from_storage
+                .keys()
+                .chain(to_storage.keys())
+                .copied()
+                .collect();
+
+            let storage: HashMap<U256, Diff<U256>> = indices
+                .into_iter()
+                .filter_map(|index| {
+                    let diff = Diff::new(
+                        from_storage.get(&index).copied(),
+                        to_storage.get(&index).copied(),
+                    );
+                    (!diff.is_same()).then_some((index, diff))
+                })
+                .collect();
+
+            let account_diff = AccountDiff {
+                balance,
+                nonce,
+                code,
+                storage,
+            };
+
+            if !account_diff.is_unchanged() {
+                accounts.insert(address, account_diff);
+            }
+        }
+
+        StateDiff { accounts }
+    }
+
+    /// Merges all layers above `layer_id` down into it, keeping their mutations while reducing
+    /// the stack's depth, instead of discarding them like [`LayeredDatabase::revert_to_layer`]
+    /// does. Use this to periodically flatten finalized state so lookup latency stays flat
+    /// rather than growing with the number of surviving checkpoints/transactions.
+    pub fn canonicalize_to_layer(&mut self, layer_id: usize) {
+        assert!(layer_id < self.stack.len(), "Invalid layer id.");
+
+        for layer in self.stack.split_off(layer_id + 1) {
+            let base = &mut self.stack[layer_id];
+
+            for (address, account_info) in layer.account_infos {
+                base.account_infos.insert(address, account_info);
+            }
+
+            for (address, slots) in layer.storage {
+                base.storage.entry(address).or_default().extend(slots);
+            }
+
+            base.contracts.extend(layer.contracts);
+            base.block_hashes.extend(layer.block_hashes);
+        }
+
+        self.cache.invalidate_above(layer_id);
+        self.transaction_root_layer_id = self.transaction_root_layer_id.min(layer_id);
+    }
 }
 
 impl Database for LayeredDatabase<RethnetLayer> {
     type Error = anyhow::Error;
 
     fn basic(&mut self, address: Address) -> anyhow::Result<Option<AccountInfo>> {
-        let account = self
+        if let Some(account) = self.cache.get_account(&address) {
+            return Ok(Some(account));
+        }
+
+        let found = self
+            .stack
             .iter()
-            .find_map(|layer| layer.account_infos.get(&address).cloned());
+            .enumerate()
+            .rev()
+            .find_map(|(layer_id, layer)| {
+                layer
+                    .account_infos
+                    .get(&address)
+                    .map(|account| (layer_id, account.clone()))
+            });
 
-        log::debug!("account with address `{}`: {:?}", address, account);
+        log::debug!("account with address `{}`: {:?}", address, found);
 
-        // TODO: Move this out of LayeredDatabase when forking
-        Ok(account.or(Some(AccountInfo {
-            balance: U256::ZERO,
-            nonce: 0,
-            code_hash: KECCAK_EMPTY,
-            code: None,
-        })))
+        let account = match found {
+            Some((layer_id, account)) => {
+                self.cache.put_account(address, account.clone(), layer_id);
+                account
+            }
+            // A standalone (non-forked) `LayeredDatabase` treats every unknown address as an
+            // empty, pre-existing account; `ForkedDatabase` overrides this by only returning
+            // `Some` once the remote node confirms the account actually exists.
+            None => AccountInfo {
+                balance: U256::ZERO,
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        };
+
+        Ok(Some(account))
     }
 
     fn code_by_hash(&mut self, code_hash: H256) -> anyhow::Result<Bytecode> {
-        self.iter()
-            .find_map(|layer| {
-                layer.contracts.get(&code_hash).map(|bytecode| unsafe {
-                    Bytecode::new_raw_with_hash(bytecode.clone(), code_hash)
-                })
+        if let Some(code) = self.cache.get_code(&code_hash) {
+            return Ok(code);
+        }
+
+        let found = self
+            .stack
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(layer_id, layer)| {
+                layer
+                    .contracts
+                    .get(&code_hash)
+                    .map(|bytecode| (layer_id, bytecode.clone()))
             })
             .ok_or_else(|| {
                 anyhow!(
                     "Layered database does not contain contract with code hash: {}.",
                     code_hash,
                 )
-            })
+            })?;
+
+        let (layer_id, bytecode) = found;
+        let code = unsafe { Bytecode::new_raw_with_hash(bytecode, code_hash) };
+        self.cache.put_code(code_hash, code.clone(), layer_id);
+
+        Ok(code)
     }
 
     fn storage(&mut self, address: Address, index: U256) -> anyhow::Result<U256> {
-        self.iter()
-            .find_map(|layer| {
+        if let Some(value) = self.cache.get_storage(&address, &index) {
+            return Ok(value);
+        }
+
+        let found = self
+            .stack
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(layer_id, layer)| {
                 layer
                     .storage
                     .get(&address)
                     .and_then(|storage| storage.get(&index))
-                    .cloned()
+                    .map(|value| (layer_id, *value))
             })
             .ok_or_else(|| {
                 anyhow!(
@@ -206,7 +494,12 @@ impl Database for LayeredDatabase<RethnetLayer> {
                     address,
                     index
                 )
-            })
+            })?;
+
+        let (layer_id, value) = found;
+        self.cache.put_storage(address, index, value, layer_id);
+
+        Ok(value)
     }
 
     fn block_hash(&mut self, number: U256) -> anyhow::Result<H256> {
@@ -223,13 +516,27 @@ impl Database for LayeredDatabase<RethnetLayer> {
 
 impl DatabaseCommit for LayeredDatabase<RethnetLayer> {
     fn commit(&mut self, changes: HashMap<Address, Account>) {
-        let last_layer = self.last_layer_mut();
+        let layer_id = self.last_layer_id();
+        let Self {
+            stack,
+            cache,
+            ..
+        } = self;
+        let last_layer = stack.last_mut().unwrap();
 
         changes.into_iter().for_each(|(address, account)| {
             if account.is_empty() || account.is_destroyed {
                 last_layer.account_infos.remove(&address);
+                cache.invalidate_account(&address);
             } else {
-                last_layer.insert_account(address, account.info);
+                if account.storage_cleared {
+                    // The layer's storage for this account is cleared below; drop its cached
+                    // slots too, so they aren't served after the clear.
+                    cache.invalidate_account(&address);
+                }
+
+                last_layer.insert_account(address, account.info.clone());
+                cache.put_account(address, account.info, layer_id);
 
                 let storage = last_layer
                     .storage
@@ -245,8 +552,10 @@ impl DatabaseCommit for LayeredDatabase<RethnetLayer> {
                     let value = value.present_value();
                     if value == U256::ZERO {
                         storage.remove(&index);
+                        cache.invalidate_storage(&address, &index);
                     } else {
                         storage.insert(index, value);
+                        cache.put_storage(address, index, value, layer_id);
                     }
                 });
 
@@ -270,6 +579,10 @@ impl DatabaseDebug for LayeredDatabase<RethnetLayer> {
             .account_infos
             .insert(address, account_info);
 
+        // `basic()` may already have cached this address from before the insert; without this,
+        // the next read would keep serving the stale pre-insert account.
+        self.cache.invalidate_account(&address);
+
         Ok(())
     }
 
@@ -286,10 +599,16 @@ impl DatabaseDebug for LayeredDatabase<RethnetLayer> {
         address: Address,
         modifier: Box<dyn Fn(&mut AccountInfo) + Send>,
     ) -> Result<(), Self::Error> {
-        // TODO: Move account insertion out of LayeredDatabase when forking
+        // `account_or_insert_mut` fabricates a fresh account when one isn't already present in
+        // any layer; `ForkedDatabase` pre-populates the top layer from the remote node before
+        // modifying it, so this never shadows genuinely remote-backed state.
         let account_info = self.account_or_insert_mut(&address);
         modifier(account_info);
 
+        // The cache may hold a pre-modification snapshot of this address; invalidate it so the
+        // next `basic()` re-reads the layer we just mutated instead of returning stale data.
+        self.cache.invalidate_account(&address);
+
         Ok(())
     }
 
@@ -297,20 +616,66 @@ impl DatabaseDebug for LayeredDatabase<RethnetLayer> {
         // We cannot actually remove an account in a layered database, so instead set the empty account
         let empty_account = AccountInfo::default();
 
-        if let Some(account_info) = self.last_layer_mut().account_infos.get_mut(&address) {
-            let old_account_info = account_info.clone();
+        let old_account_info =
+            if let Some(account_info) = self.last_layer_mut().account_infos.get_mut(&address) {
+                let old_account_info = account_info.clone();
 
-            *account_info = empty_account;
+                *account_info = empty_account;
 
-            Ok(Some(old_account_info))
-        } else {
-            self.last_layer_mut().insert_account(address, empty_account);
-            Ok(None)
-        }
+                Some(old_account_info)
+            } else {
+                // The account may still exist in a lower layer.
+                let old_account_info = self.account(&address).cloned();
+                self.last_layer_mut().insert_account(address, empty_account);
+                old_account_info
+            };
+
+        self.cache.invalidate_account(&address);
+
+        Ok(old_account_info)
+    }
+
+    fn set_account_storage_slot(
+        &mut self,
+        address: Address,
+        index: U256,
+        value: U256,
+    ) -> Result<(), Self::Error> {
+        self.insert_storage_slot(address, index, value);
+        Ok(())
     }
 
     fn storage_root(&mut self) -> Result<H256, Self::Error> {
-        todo!()
+        let addresses: HashSet<Address> = self
+            .iter()
+            .flat_map(|layer| layer.account_infos.keys().copied())
+            .collect();
+
+        let mut state_trie = Trie::default();
+
+        for address in addresses {
+            let Some(account_info) = self.account(&address) else {
+                continue;
+            };
+
+            // An account that was explicitly removed is represented as a default `AccountInfo`
+            // (see `remove_account`); such an account no longer exists in state.
+            if account_info == AccountInfo::default() {
+                continue;
+            }
+
+            let storage_root = self.account_storage_root(&address);
+
+            let mut account_rlp = RlpStream::new_list(4);
+            account_rlp.append(&account_info.nonce);
+            account_rlp.append(&account_info.balance);
+            account_rlp.append(&storage_root);
+            account_rlp.append(&account_info.code_hash);
+
+            state_trie.insert(&trie::state_trie_key(&address), account_rlp.out().to_vec());
+        }
+
+        Ok(state_trie.root_hash())
     }
 
     fn checkpoint(&mut self) -> Result<(), Self::Error> {
@@ -327,4 +692,258 @@ impl DatabaseDebug for LayeredDatabase<RethnetLayer> {
             Err(anyhow!("No checkpoints to revert."))
         }
     }
+}
+
+#[async_trait::async_trait]
+impl Prefetch for LayeredDatabase<RethnetLayer> {
+    async fn prefetch(
+        &mut self,
+        _accounts: &[Address],
+        _storage: &[(Address, Vec<U256>)],
+    ) -> anyhow::Result<()> {
+        // Every layer already lives in memory, so there's nothing to warm.
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> Address {
+        Address::from([byte; 20])
+    }
+
+    #[test]
+    fn checkpoint_storage_ignores_writes_in_the_current_top_layer() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+        let addr = address(1);
+        let index = U256::from(1);
+
+        db.insert_storage_slot(addr, index, U256::from(1));
+        db.add_layer_default();
+        db.insert_storage_slot(addr, index, U256::from(2));
+
+        assert_eq!(db.checkpoint_storage(&addr, &index), U256::from(1));
+    }
+
+    #[test]
+    fn original_storage_ignores_writes_after_the_transaction_started() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+        let addr = address(1);
+        let index = U256::from(1);
+
+        db.insert_storage_slot(addr, index, U256::from(1));
+        db.start_transaction();
+
+        db.add_layer_default();
+        db.insert_storage_slot(addr, index, U256::from(2));
+
+        assert_eq!(db.original_storage(&addr, &index), U256::from(1));
+    }
+
+    #[test]
+    fn original_storage_defaults_to_zero_for_unwritten_slots() {
+        let db = LayeredDatabase::<RethnetLayer>::default();
+        assert_eq!(db.original_storage(&address(1), &U256::from(1)), U256::ZERO);
+    }
+
+    #[test]
+    fn diff_classifies_born_changed_and_died_accounts() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+
+        let born = address(1);
+        let changed = address(2);
+        let died = address(3);
+
+        db.last_layer_mut().insert_account(
+            changed,
+            AccountInfo {
+                balance: U256::from(1),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        db.last_layer_mut().insert_account(
+            died,
+            AccountInfo {
+                balance: U256::from(1),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        let from_layer_id = db.add_layer_default().0;
+
+        db.last_layer_mut().insert_account(
+            born,
+            AccountInfo {
+                balance: U256::from(1),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        db.last_layer_mut().insert_account(
+            changed,
+            AccountInfo {
+                balance: U256::from(2),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        db.last_layer_mut()
+            .insert_account(died, AccountInfo::default());
+        let to_layer_id = db.last_layer_id();
+
+        let diff = db.diff(from_layer_id, to_layer_id);
+
+        assert_eq!(
+            diff.accounts.get(&born).unwrap().balance,
+            Diff::Born(U256::from(1))
+        );
+        assert_eq!(
+            diff.accounts.get(&changed).unwrap().balance,
+            Diff::Changed(U256::from(1), U256::from(2))
+        );
+        assert_eq!(
+            diff.accounts.get(&died).unwrap().balance,
+            Diff::Died(U256::from(1))
+        );
+    }
+
+    #[test]
+    fn diff_omits_accounts_that_did_not_change() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+        let unchanged = address(1);
+
+        db.last_layer_mut().insert_account(
+            unchanged,
+            AccountInfo {
+                balance: U256::from(1),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        let from_layer_id = db.last_layer_id();
+        let to_layer_id = db.add_layer_default().0;
+
+        let diff = db.diff(from_layer_id, to_layer_id);
+
+        assert!(diff.accounts.is_empty());
+    }
+
+    #[test]
+    fn canonicalize_to_layer_squashes_layers_without_losing_writes() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+        let addr = address(1);
+        let index = U256::from(1);
+
+        db.insert_storage_slot(addr, index, U256::from(1));
+        db.add_layer_default();
+        db.last_layer_mut().insert_account(
+            addr,
+            AccountInfo {
+                balance: U256::from(5),
+                nonce: 1,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        db.add_layer_default();
+        db.insert_storage_slot(addr, U256::from(2), U256::from(99));
+
+        assert_eq!(db.last_layer_id(), 2);
+
+        db.canonicalize_to_layer(0);
+
+        assert_eq!(db.last_layer_id(), 0);
+        assert_eq!(db.account(&addr).unwrap().balance, U256::from(5));
+        assert_eq!(db.storage_as_of(&addr, 0), {
+            let mut expected = HashMap::new();
+            expected.insert(U256::from(1), U256::from(1));
+            expected.insert(U256::from(2), U256::from(99));
+            expected
+        });
+    }
+
+    #[test]
+    fn insert_account_invalidates_the_cached_entry() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+        let addr = address(1);
+
+        db.last_layer_mut().insert_account(
+            addr,
+            AccountInfo {
+                balance: U256::from(1),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        // Warm the cache with the pre-insert account.
+        assert_eq!(db.basic(addr).unwrap().unwrap().balance, U256::from(1));
+
+        DatabaseDebug::insert_account(
+            &mut db,
+            addr,
+            AccountInfo {
+                balance: U256::from(2),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(db.basic(addr).unwrap().unwrap().balance, U256::from(2));
+    }
+
+    #[test]
+    fn modify_account_invalidates_the_cached_entry() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+        let addr = address(1);
+
+        db.last_layer_mut().insert_account(
+            addr,
+            AccountInfo {
+                balance: U256::from(1),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        // Warm the cache with the pre-modification account.
+        assert_eq!(db.basic(addr).unwrap().unwrap().balance, U256::from(1));
+
+        db.modify_account(addr, Box::new(|account| account.balance = U256::from(2)))
+            .unwrap();
+
+        assert_eq!(db.basic(addr).unwrap().unwrap().balance, U256::from(2));
+    }
+
+    #[test]
+    fn remove_account_invalidates_the_cached_entry() {
+        let mut db = LayeredDatabase::<RethnetLayer>::default();
+        let addr = address(1);
+
+        db.last_layer_mut().insert_account(
+            addr,
+            AccountInfo {
+                balance: U256::from(1),
+                nonce: 0,
+                code_hash: KECCAK_EMPTY,
+                code: None,
+            },
+        );
+        // Warm the cache with the pre-removal account.
+        assert_eq!(db.basic(addr).unwrap().unwrap().balance, U256::from(1));
+
+        db.remove_account(addr).unwrap();
+
+        assert_eq!(db.basic(addr).unwrap().unwrap().balance, U256::ZERO);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,10 @@
+mod cache;
+mod diff;
+mod layered_db;
+mod remote;
+mod trie;
+
+pub use cache::CacheLimits;
+pub use diff::{AccountDiff, Diff, StateDiff};
+pub use layered_db::{LayeredDatabase, RethnetLayer};
+pub use remote::{ForkConfig, ForkedDatabase};
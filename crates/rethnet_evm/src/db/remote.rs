@@ -0,0 +1,481 @@
+use anyhow::Context;
+use hashbrown::{HashMap, HashSet};
+use rethnet_eth::{Address, Bytes, H256, U256};
+use revm::{Account, AccountInfo, Bytecode, Database, DatabaseCommit, KECCAK_EMPTY};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{DatabaseDebug, Prefetch};
+
+use super::{LayeredDatabase, RethnetLayer};
+
+/// The number of concurrent in-flight JSON-RPC requests [`ForkedDatabase::prefetch`] allows
+/// itself, so a large access list doesn't open hundreds of sockets against the forked node at
+/// once.
+const PARALLEL_QUERY_BATCH_SIZE: usize = 8;
+
+/// Pins a [`ForkedDatabase`] to a specific remote JSON-RPC endpoint and block number.
+#[derive(Debug, Clone)]
+pub struct ForkConfig {
+    /// The HTTP(S) URL of the upstream JSON-RPC node.
+    pub url: String,
+    /// The block number that forked state is read as-of.
+    pub block_number: U256,
+}
+
+/// A [`LayeredDatabase`] that, on a local miss, lazily fetches state from a remote JSON-RPC node
+/// pinned to [`ForkConfig::block_number`]. Every fetched account, storage slot and block hash is
+/// memoized into the top layer of the layered database, so repeat lookups don't hit the network;
+/// reverting that layer also discards what was memoized into it, and the next lookup fetches it
+/// again. An account the remote node reports as absent is not memoized.
+pub struct ForkedDatabase {
+    db: LayeredDatabase<RethnetLayer>,
+    remote: RemoteClient,
+}
+
+impl ForkedDatabase {
+    /// Creates a [`ForkedDatabase`] forking from the upstream node described by `config`.
+    pub fn new(config: ForkConfig) -> Self {
+        Self {
+            db: LayeredDatabase::default(),
+            remote: RemoteClient::new(config),
+        }
+    }
+}
+
+impl Database for ForkedDatabase {
+    type Error = anyhow::Error;
+
+    fn basic(&mut self, address: Address) -> anyhow::Result<Option<AccountInfo>> {
+        if let Some(account_info) = self.db.account(&address) {
+            return Ok(Some(account_info.clone()));
+        }
+
+        let balance = self.remote.get_balance(&address)?;
+        let nonce = self.remote.get_transaction_count(&address)?;
+        let code = self.remote.get_code(&address)?;
+
+        // A genuinely-absent account stays absent; unlike the bare `LayeredDatabase`, we don't
+        // fabricate an empty one once the remote node has confirmed it doesn't exist.
+        if balance.is_zero() && nonce == 0 && code.is_empty() {
+            return Ok(None);
+        }
+
+        let mut account_info = AccountInfo {
+            balance,
+            nonce,
+            code_hash: KECCAK_EMPTY,
+            code: None,
+        };
+
+        if !code.is_empty() {
+            let bytecode = Bytecode::new_raw(code);
+            account_info.code_hash = bytecode.hash();
+            account_info.code = Some(bytecode);
+        }
+
+        // Memoize via `RethnetLayer::insert_account` directly, rather than the `DatabaseDebug`
+        // trait method, so the fetched bytecode is also recorded in `contracts`; otherwise a
+        // subsequent `code_by_hash` lookup for this forked contract would fail to find it.
+        self.db.last_layer_mut().insert_account(address, account_info.clone());
+
+        Ok(Some(account_info))
+    }
+
+    fn code_by_hash(&mut self, code_hash: H256) -> anyhow::Result<Bytecode> {
+        // Code is always fetched and memoized alongside its account in `basic`.
+        self.db.code_by_hash(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> anyhow::Result<U256> {
+        if let Ok(value) = self.db.storage(address, index) {
+            return Ok(value);
+        }
+
+        let value = self.remote.get_storage_at(&address, &index)?;
+        self.db.insert_storage_slot(address, index, value);
+
+        Ok(value)
+    }
+
+    fn block_hash(&mut self, number: U256) -> anyhow::Result<H256> {
+        if let Ok(hash) = self.db.block_hash(number) {
+            return Ok(hash);
+        }
+
+        let hash = self.remote.get_block_hash(&number)?;
+        self.db.insert_block(number, hash)?;
+
+        Ok(hash)
+    }
+}
+
+impl DatabaseCommit for ForkedDatabase {
+    fn commit(&mut self, changes: HashMap<Address, Account>) {
+        self.db.commit(changes)
+    }
+}
+
+impl DatabaseDebug for ForkedDatabase {
+    type Error = anyhow::Error;
+
+    fn insert_account(
+        &mut self,
+        address: Address,
+        account_info: AccountInfo,
+    ) -> Result<(), Self::Error> {
+        self.db.insert_account(address, account_info)
+    }
+
+    fn insert_block(&mut self, block_number: U256, block_hash: H256) -> Result<(), Self::Error> {
+        self.db.insert_block(block_number, block_hash)
+    }
+
+    fn modify_account(
+        &mut self,
+        address: Address,
+        modifier: Box<dyn Fn(&mut AccountInfo) + Send>,
+    ) -> Result<(), Self::Error> {
+        // Pre-populate the account from the remote node first; otherwise `self.db` has never
+        // seen this address and `modify_account` would fabricate a fresh, empty account instead
+        // of modifying the real remote-backed one.
+        self.basic(address)?;
+
+        self.db.modify_account(address, modifier)
+    }
+
+    fn remove_account(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        // Fetch the account first, so a remote-backed account that hasn't been read yet is
+        // still returned as the removed account.
+        self.basic(address)?;
+
+        self.db.remove_account(address)
+    }
+
+    fn set_account_storage_slot(
+        &mut self,
+        address: Address,
+        index: U256,
+        value: U256,
+    ) -> Result<(), Self::Error> {
+        self.db.set_account_storage_slot(address, index, value)
+    }
+
+    fn storage_root(&mut self) -> Result<H256, Self::Error> {
+        self.db.storage_root()
+    }
+
+    fn checkpoint(&mut self) -> Result<(), Self::Error> {
+        self.db.checkpoint()
+    }
+
+    fn revert(&mut self) -> Result<(), Self::Error> {
+        self.db.revert()
+    }
+}
+
+#[async_trait::async_trait]
+impl Prefetch for ForkedDatabase {
+    async fn prefetch(
+        &mut self,
+        accounts: &[Address],
+        storage: &[(Address, Vec<U256>)],
+    ) -> anyhow::Result<()> {
+        let mut pending_accounts: Vec<Address> = accounts
+            .iter()
+            .copied()
+            .filter(|address| self.db.account(address).is_none())
+            .collect();
+        // `accounts` isn't necessarily sorted (e.g. an access list's entries come in whatever
+        // order the transaction specified), so `Vec::dedup`'s consecutive-only check would miss
+        // non-adjacent duplicates and still fetch the same address twice.
+        let mut seen = HashSet::new();
+        pending_accounts.retain(|address| seen.insert(*address));
+
+        for batch in pending_accounts.chunks(PARALLEL_QUERY_BATCH_SIZE) {
+            let fetched = futures::future::join_all(batch.iter().copied().map(|address| {
+                let remote = self.remote.clone();
+                async move {
+                    let balance = remote.get_balance_async(&address).await?;
+                    let nonce = remote.get_transaction_count_async(&address).await?;
+                    let code = remote.get_code_async(&address).await?;
+                    Ok::<_, anyhow::Error>((address, balance, nonce, code))
+                }
+            }))
+            .await;
+
+            for (address, balance, nonce, code) in fetched.into_iter().collect::<Result<Vec<_>, _>>()? {
+                // Mirrors the "confirmed absent" handling in `Database::basic`: don't fabricate
+                // an account the remote node doesn't actually have.
+                if balance.is_zero() && nonce == 0 && code.is_empty() {
+                    continue;
+                }
+
+                let mut account_info = AccountInfo {
+                    balance,
+                    nonce,
+                    code_hash: KECCAK_EMPTY,
+                    code: None,
+                };
+
+                if !code.is_empty() {
+                    let bytecode = Bytecode::new_raw(code);
+                    account_info.code_hash = bytecode.hash();
+                    account_info.code = Some(bytecode);
+                }
+
+                // See the equivalent memoization in `Database::basic`: use `RethnetLayer::insert_account`
+                // directly so the fetched bytecode is also recorded in `contracts`.
+                self.db.last_layer_mut().insert_account(address, account_info);
+            }
+        }
+
+        let mut pending_storage: Vec<(Address, U256)> = storage
+            .iter()
+            .flat_map(|(address, indices)| indices.iter().map(move |index| (*address, *index)))
+            .filter(|(address, index)| self.db.storage(*address, *index).is_err())
+            .collect();
+        let mut seen = HashSet::new();
+        pending_storage.retain(|slot| seen.insert(*slot));
+
+        for batch in pending_storage.chunks(PARALLEL_QUERY_BATCH_SIZE) {
+            let fetched = futures::future::join_all(batch.iter().copied().map(|(address, index)| {
+                let remote = self.remote.clone();
+                async move {
+                    let value = remote.get_storage_at_async(&address, &index).await?;
+                    Ok::<_, anyhow::Error>((address, index, value))
+                }
+            }))
+            .await;
+
+            for (address, index, value) in fetched.into_iter().collect::<Result<Vec<_>, _>>()? {
+                self.db.insert_storage_slot(address, index, value);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A minimal JSON-RPC client used to lazily fetch state from the node backing a fork. The
+/// `Database` trait this feeds into is itself synchronous, so the blocking `get_*` methods bridge
+/// into the async `get_*_async` ones via [`tokio::task::block_in_place`] (which requires them to
+/// only ever be called from a multi-threaded runtime's worker thread). [`ForkedDatabase::prefetch`]
+/// calls the `_async` methods directly, so several requests can be in flight at once.
+#[derive(Debug, Clone)]
+struct RemoteClient {
+    http: reqwest::Client,
+    url: String,
+    block_number: U256,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+}
+
+impl RemoteClient {
+    fn new(config: ForkConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: config.url,
+            block_number: config.block_number,
+        }
+    }
+
+    fn block_tag(&self) -> Value {
+        json!(format!("0x{:x}", self.block_number))
+    }
+
+    fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(self.call_async(method, params))
+        })
+    }
+
+    async fn call_async(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response: RpcResponse = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await
+            .with_context(|| format!("Failed to call `{method}` on forked node `{}`", self.url))?;
+
+        response
+            .result
+            .ok_or_else(|| anyhow::anyhow!("Forked node returned no result for `{method}`"))
+    }
+
+    fn get_balance(&self, address: &Address) -> anyhow::Result<U256> {
+        let result = self.call(
+            "eth_getBalance",
+            json!([format!("{address:?}"), self.block_tag()]),
+        )?;
+        parse_u256(&result)
+    }
+
+    async fn get_balance_async(&self, address: &Address) -> anyhow::Result<U256> {
+        let result = self
+            .call_async(
+                "eth_getBalance",
+                json!([format!("{address:?}"), self.block_tag()]),
+            )
+            .await?;
+        parse_u256(&result)
+    }
+
+    fn get_transaction_count(&self, address: &Address) -> anyhow::Result<u64> {
+        let result = self.call(
+            "eth_getTransactionCount",
+            json!([format!("{address:?}"), self.block_tag()]),
+        )?;
+        Ok(parse_u256(&result)?.as_u64())
+    }
+
+    async fn get_transaction_count_async(&self, address: &Address) -> anyhow::Result<u64> {
+        let result = self
+            .call_async(
+                "eth_getTransactionCount",
+                json!([format!("{address:?}"), self.block_tag()]),
+            )
+            .await?;
+        Ok(parse_u256(&result)?.as_u64())
+    }
+
+    fn get_code(&self, address: &Address) -> anyhow::Result<Bytes> {
+        let result = self.call(
+            "eth_getCode",
+            json!([format!("{address:?}"), self.block_tag()]),
+        )?;
+        parse_bytes(&result)
+    }
+
+    async fn get_code_async(&self, address: &Address) -> anyhow::Result<Bytes> {
+        let result = self
+            .call_async(
+                "eth_getCode",
+                json!([format!("{address:?}"), self.block_tag()]),
+            )
+            .await?;
+        parse_bytes(&result)
+    }
+
+    fn get_storage_at(&self, address: &Address, index: &U256) -> anyhow::Result<U256> {
+        let result = self.call(
+            "eth_getStorageAt",
+            json!([
+                format!("{address:?}"),
+                format!("0x{index:x}"),
+                self.block_tag()
+            ]),
+        )?;
+        parse_u256(&result)
+    }
+
+    async fn get_storage_at_async(&self, address: &Address, index: &U256) -> anyhow::Result<U256> {
+        let result = self
+            .call_async(
+                "eth_getStorageAt",
+                json!([
+                    format!("{address:?}"),
+                    format!("0x{index:x}"),
+                    self.block_tag()
+                ]),
+            )
+            .await?;
+        parse_u256(&result)
+    }
+
+    fn get_block_hash(&self, number: &U256) -> anyhow::Result<H256> {
+        let result = self.call("eth_getBlockByNumber", json!([format!("0x{number:x}"), false]))?;
+        parse_block_hash(&result)
+    }
+}
+
+fn parse_u256(value: &Value) -> anyhow::Result<U256> {
+    let hex = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Expected a hex string"))?;
+
+    Ok(U256::from_str_radix(hex.trim_start_matches("0x"), 16)?)
+}
+
+fn parse_bytes(value: &Value) -> anyhow::Result<Bytes> {
+    let hex = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Expected a hex string"))?;
+
+    Ok(Bytes::from(hex::decode(hex.trim_start_matches("0x"))?))
+}
+
+fn parse_block_hash(value: &Value) -> anyhow::Result<H256> {
+    let hash = value
+        .get("hash")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("Forked node response is missing a block hash"))?;
+
+    Ok(hash.parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_does_not_eagerly_fetch_from_the_remote_node() {
+        // Constructing a `ForkedDatabase` must not touch the network; state is only ever fetched
+        // lazily, the first time a given account/slot/block hash is actually requested.
+        let db = ForkedDatabase::new(ForkConfig {
+            url: "http://localhost:1".to_string(),
+            block_number: U256::from(100),
+        });
+
+        assert_eq!(db.db.last_layer_id(), 0);
+    }
+
+    #[test]
+    fn parse_u256_reads_hex_quantity() {
+        let value = json!("0x2a");
+        assert_eq!(parse_u256(&value).unwrap(), U256::from(42));
+    }
+
+    #[test]
+    fn parse_u256_rejects_non_string() {
+        assert!(parse_u256(&json!(42)).is_err());
+    }
+
+    #[test]
+    fn parse_bytes_decodes_hex_payload() {
+        let value = json!("0x1234");
+        assert_eq!(parse_bytes(&value).unwrap(), Bytes::from(vec![0x12, 0x34]));
+    }
+
+    #[test]
+    fn parse_bytes_handles_empty_code() {
+        let value = json!("0x");
+        assert_eq!(parse_bytes(&value).unwrap(), Bytes::from(vec![]));
+    }
+
+    #[test]
+    fn parse_block_hash_reads_the_hash_field() {
+        let hash = H256::from([7; 32]);
+        let value = json!({ "hash": format!("{hash:?}") });
+
+        assert_eq!(parse_block_hash(&value).unwrap(), hash);
+    }
+
+    #[test]
+    fn parse_block_hash_rejects_missing_field() {
+        assert!(parse_block_hash(&json!({})).is_err());
+    }
+}
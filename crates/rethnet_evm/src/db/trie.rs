@@ -0,0 +1,354 @@
+use hashbrown::HashMap;
+use rethnet_eth::{Address, H256, U256};
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+/// The Keccak-256 hash of the RLP encoding of an empty byte array (`0x80`).
+pub const KECCAK_NULL_RLP: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Computes the Keccak-256 hash of the provided bytes.
+pub fn keccak256(bytes: &[u8]) -> H256 {
+    H256::from_slice(&Keccak256::digest(bytes))
+}
+
+/// Converts `address` into the key used to index it in the secure state trie.
+pub fn state_trie_key(address: &Address) -> H256 {
+    keccak256(address.as_bytes())
+}
+
+/// Converts a storage `index` into the key used to index it in the secure storage trie.
+pub fn storage_trie_key(index: &U256) -> H256 {
+    let mut bytes = [0u8; 32];
+    index.to_big_endian(&mut bytes);
+    keccak256(&bytes)
+}
+
+/// RLP-encodes `value` using the minimal big-endian representation (no leading zero bytes).
+pub fn rlp_encode_u256(value: &U256) -> Vec<u8> {
+    let mut stream = RlpStream::new();
+    stream.append(value);
+    stream.out().to_vec()
+}
+
+fn bytes_to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    nibbles
+}
+
+/// Hex-prefix encodes a nibble sequence, per the Ethereum yellow paper's `HP` function.
+fn hex_prefix_encode(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let odd = nibbles.len() % 2 == 1;
+    let flag = (if is_leaf { 2 } else { 0 }) + (if odd { 1 } else { 0 });
+
+    let mut result = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let mut iter = nibbles.iter().copied();
+
+    if odd {
+        let nibble = iter.next().unwrap();
+        result.push((flag << 4) | nibble);
+    } else {
+        result.push(flag << 4);
+    }
+
+    while let Some(high) = iter.next() {
+        let low = iter.next().expect("remaining nibbles come in pairs");
+        result.push((high << 4) | low);
+    }
+
+    result
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Leaf { key: Vec<u8>, value: Vec<u8> },
+    Extension { key: Vec<u8>, child: Box<Node> },
+    Branch { children: Box<[Node; 16]>, value: Option<Vec<u8>> },
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Node::Empty
+    }
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+impl Node {
+    fn insert(self, key: &[u8], value: Vec<u8>) -> Node {
+        match self {
+            Node::Empty => Node::Leaf {
+                key: key.to_vec(),
+                value,
+            },
+            Node::Leaf {
+                key: leaf_key,
+                value: leaf_value,
+            } => {
+                let prefix_len = common_prefix_len(&leaf_key, key);
+
+                if prefix_len == leaf_key.len() && prefix_len == key.len() {
+                    return Node::Leaf {
+                        key: leaf_key,
+                        value,
+                    };
+                }
+
+                let mut children: [Node; 16] = Default::default();
+                let mut branch_value = None;
+
+                if prefix_len == leaf_key.len() {
+                    branch_value = Some(leaf_value);
+                } else {
+                    let nibble = leaf_key[prefix_len];
+                    children[nibble as usize] = Node::Leaf {
+                        key: leaf_key[prefix_len + 1..].to_vec(),
+                        value: leaf_value,
+                    };
+                }
+
+                if prefix_len == key.len() {
+                    branch_value = Some(value);
+                } else {
+                    let nibble = key[prefix_len];
+                    children[nibble as usize] = Node::Leaf {
+                        key: key[prefix_len + 1..].to_vec(),
+                        value,
+                    };
+                }
+
+                let branch = Node::Branch {
+                    children: Box::new(children),
+                    value: branch_value,
+                };
+
+                if prefix_len == 0 {
+                    branch
+                } else {
+                    Node::Extension {
+                        key: key[..prefix_len].to_vec(),
+                        child: Box::new(branch),
+                    }
+                }
+            }
+            Node::Extension {
+                key: ext_key,
+                child,
+            } => {
+                let prefix_len = common_prefix_len(&ext_key, key);
+
+                if prefix_len == ext_key.len() {
+                    let child = child.insert(&key[prefix_len..], value);
+                    return Node::Extension {
+                        key: ext_key,
+                        child: Box::new(child),
+                    };
+                }
+
+                let mut children: [Node; 16] = Default::default();
+
+                let ext_nibble = ext_key[prefix_len];
+                let remainder = &ext_key[prefix_len + 1..];
+                children[ext_nibble as usize] = if remainder.is_empty() {
+                    *child
+                } else {
+                    Node::Extension {
+                        key: remainder.to_vec(),
+                        child,
+                    }
+                };
+
+                let mut branch_value = None;
+                if prefix_len == key.len() {
+                    branch_value = Some(value);
+                } else {
+                    let nibble = key[prefix_len];
+                    children[nibble as usize] = Node::Leaf {
+                        key: key[prefix_len + 1..].to_vec(),
+                        value,
+                    };
+                }
+
+                let branch = Node::Branch {
+                    children: Box::new(children),
+                    value: branch_value,
+                };
+
+                if prefix_len == 0 {
+                    branch
+                } else {
+                    Node::Extension {
+                        key: key[..prefix_len].to_vec(),
+                        child: Box::new(branch),
+                    }
+                }
+            }
+            Node::Branch {
+                mut children,
+                value: branch_value,
+            } => {
+                if key.is_empty() {
+                    return Node::Branch {
+                        children,
+                        value: Some(value),
+                    };
+                }
+
+                let nibble = key[0] as usize;
+                let child = std::mem::take(&mut children[nibble]);
+                children[nibble] = child.insert(&key[1..], value);
+
+                Node::Branch {
+                    children,
+                    value: branch_value,
+                }
+            }
+        }
+    }
+
+    /// Returns the node's raw RLP encoding. Replacing an encoding of 32 bytes or more by its
+    /// Keccak-256 hash, per the Ethereum "node composition" rule, is left to [`encode_child`].
+    fn encode(&self) -> Vec<u8> {
+        let rlp = match self {
+            Node::Empty => {
+                let mut stream = RlpStream::new();
+                stream.append_empty_data();
+                return stream.out().to_vec();
+            }
+            Node::Leaf { key, value } => {
+                let mut stream = RlpStream::new_list(2);
+                stream.append(&hex_prefix_encode(key, true));
+                stream.append(value);
+                stream.out().to_vec()
+            }
+            Node::Extension { key, child } => {
+                let mut stream = RlpStream::new_list(2);
+                stream.append(&hex_prefix_encode(key, false));
+                stream.append_raw(&encode_child(child), 1);
+                stream.out().to_vec()
+            }
+            Node::Branch { children, value } => {
+                let mut stream = RlpStream::new_list(17);
+                for child in children.iter() {
+                    stream.append_raw(&encode_child(child), 1);
+                }
+                match value {
+                    Some(value) => stream.append(value),
+                    None => stream.append_empty_data(),
+                };
+                stream.out().to_vec()
+            }
+        };
+
+        rlp
+    }
+}
+
+/// Encodes a child node for embedding into its parent: nodes whose RLP encoding is 32 bytes or
+/// longer are referenced by their hash, while shorter nodes are embedded inline.
+fn encode_child(node: &Node) -> Vec<u8> {
+    let encoded = node.encode();
+    if matches!(node, Node::Empty) || encoded.len() < 32 {
+        encoded
+    } else {
+        let mut stream = RlpStream::new();
+        stream.append(&keccak256(&encoded).as_bytes());
+        stream.out().to_vec()
+    }
+}
+
+/// A secure Merkle-Patricia trie, i.e. one whose keys are stored hashed so that neighbouring
+/// entries are uniformly distributed across the trie.
+#[derive(Debug, Default)]
+pub struct Trie {
+    root: Node,
+}
+
+impl Trie {
+    /// Inserts `value` at the (already-hashed) `key`.
+    pub fn insert(&mut self, key: &H256, value: Vec<u8>) {
+        let nibbles = bytes_to_nibbles(key.as_bytes());
+        self.root = std::mem::take(&mut self.root).insert(&nibbles, value);
+    }
+
+    /// Computes the root hash of the trie.
+    pub fn root_hash(&self) -> H256 {
+        match &self.root {
+            Node::Empty => KECCAK_NULL_RLP,
+            node => keccak256(&node.encode()),
+        }
+    }
+}
+
+/// Builds the storage trie root of a single account from its merged (slot -> value) view.
+pub fn storage_root(storage: &HashMap<U256, U256>) -> H256 {
+    if storage.is_empty() {
+        return KECCAK_NULL_RLP;
+    }
+
+    let mut trie = Trie::default();
+    for (index, value) in storage {
+        trie.insert(&storage_trie_key(index), rlp_encode_u256(value));
+    }
+
+    trie.root_hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Vectors taken from the Ethereum yellow paper's definition of the `HP` function.
+    #[test]
+    fn hex_prefix_encode_leaf_even() {
+        assert_eq!(hex_prefix_encode(&[1, 2, 3, 4], true), vec![0x20, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn hex_prefix_encode_leaf_odd() {
+        assert_eq!(hex_prefix_encode(&[1, 2, 3], true), vec![0x31, 0x23]);
+    }
+
+    #[test]
+    fn hex_prefix_encode_extension_even() {
+        assert_eq!(
+            hex_prefix_encode(&[0xa, 0xb, 0xc, 0xd, 0xe, 0xf], false),
+            vec![0x00, 0xab, 0xcd, 0xef]
+        );
+    }
+
+    #[test]
+    fn hex_prefix_encode_empty() {
+        assert_eq!(hex_prefix_encode(&[], true), vec![0x20]);
+        assert_eq!(hex_prefix_encode(&[], false), vec![0x00]);
+    }
+
+    #[test]
+    fn empty_trie_root_is_keccak_null_rlp() {
+        assert_eq!(storage_root(&HashMap::new()), KECCAK_NULL_RLP);
+    }
+
+    #[test]
+    fn single_slot_storage_root_matches_known_vector() {
+        let mut storage = HashMap::new();
+        storage.insert(U256::from(1), U256::from(2));
+
+        let root = storage_root(&storage);
+
+        let expected = H256([
+            0x63, 0x02, 0xd6, 0xaa, 0x5c, 0xf8, 0xbe, 0xfc, 0x2c, 0x23, 0x25, 0x41, 0x72, 0x19,
+            0x75, 0x34, 0xa8, 0x63, 0x9f, 0xc4, 0x00, 0xeb, 0x7a, 0x11, 0xfe, 0xdb, 0xb4, 0x4c,
+            0x38, 0x8e, 0x29, 0x67,
+        ]);
+        assert_eq!(root, expected);
+    }
+}
@@ -0,0 +1,71 @@
+mod db;
+
+pub use db::{AccountDiff, CacheLimits, Diff, ForkConfig, ForkedDatabase, LayeredDatabase, RethnetLayer, StateDiff};
+
+pub use rethnet_eth::{Address, Bytes, H160, H256, U256};
+pub use revm::{
+    Account, AccountInfo, Bytecode, CreateScheme, Database, DatabaseCommit, ExecutionResult,
+    Return, State, TransactOut, TransactTo, TxEnv, EVM, KECCAK_EMPTY,
+};
+
+/// Extends [`Database`] with the debugging/state-manipulation operations Hardhat needs to
+/// directly edit EVM state outside of normal transaction execution (e.g. `hardhat_setBalance`),
+/// and to save/restore checkpoints for test isolation (`evm_snapshot`/`evm_revert`).
+pub trait DatabaseDebug {
+    /// The database's error type.
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Inserts the provided `AccountInfo` at the specified `address`.
+    fn insert_account(
+        &mut self,
+        address: Address,
+        account_info: AccountInfo,
+    ) -> Result<(), Self::Error>;
+
+    /// Inserts the provided `block_hash` at the specified `block_number`.
+    fn insert_block(&mut self, block_number: U256, block_hash: H256) -> Result<(), Self::Error>;
+
+    /// Applies `modifier` to the account at `address`, inserting a default account first if one
+    /// doesn't already exist.
+    fn modify_account(
+        &mut self,
+        address: Address,
+        modifier: Box<dyn Fn(&mut AccountInfo) + Send>,
+    ) -> Result<(), Self::Error>;
+
+    /// Removes and returns the account at `address`, if it exists.
+    fn remove_account(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error>;
+
+    /// Sets the storage slot at `index` for the account at `address` to `value`.
+    fn set_account_storage_slot(
+        &mut self,
+        address: Address,
+        index: U256,
+        value: U256,
+    ) -> Result<(), Self::Error>;
+
+    /// Computes the state root over all accounts and their storage.
+    fn storage_root(&mut self) -> Result<H256, Self::Error>;
+
+    /// Opens a new checkpoint that subsequent mutations can be rolled back to with [`DatabaseDebug::revert`].
+    fn checkpoint(&mut self) -> Result<(), Self::Error>;
+
+    /// Reverts all mutations made since the most recent open checkpoint.
+    fn revert(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Lets a backend warm a batch of accounts and storage slots concurrently ahead of executing a
+/// transaction. This matters for backends where a single lookup is a network round-trip (see
+/// [`ForkedDatabase`]); a purely in-memory backend like [`LayeredDatabase`] has nothing to warm
+/// and implements this as a no-op.
+#[async_trait::async_trait]
+pub trait Prefetch {
+    /// Concurrently fetches `accounts` and the listed `storage` slots into the backend, so that
+    /// the (synchronous) [`Database`] calls a subsequent [`EVM::transact`] makes are served from
+    /// an already-warm cache instead of each blocking on its own round-trip.
+    async fn prefetch(
+        &mut self,
+        accounts: &[Address],
+        storage: &[(Address, Vec<U256>)],
+    ) -> anyhow::Result<()>;
+}
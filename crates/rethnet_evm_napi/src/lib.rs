@@ -13,8 +13,9 @@ use napi::{
 };
 use napi_derive::napi;
 use rethnet_evm::{
-    AccountInfo, Bytecode, Bytes, CreateScheme, Database, DatabaseDebug, LayeredDatabase,
-    RethnetLayer, State, TransactTo, TxEnv, EVM, H160, H256, U256,
+    AccountInfo, Bytecode, Bytes, CreateScheme, Database, DatabaseDebug, ForkConfig,
+    ForkedDatabase, LayeredDatabase, Prefetch, Return, RethnetLayer, State, TransactTo, TxEnv,
+    EVM, H160, H256, U256,
 };
 
 #[napi(constructor)]
@@ -175,6 +176,51 @@ impl From<rethnet_evm::TransactOut> for TransactionOutput {
     }
 }
 
+/// The ABI selector of Solidity's built-in `Error(string)` revert reason.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// The ABI selector of Solidity's built-in `Panic(uint256)` revert reason (e.g. a failed
+/// `assert`, a division by zero, or an out-of-bounds array access).
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// ABI-decodes a revert's output buffer, recognizing Solidity's built-in `Error(string)` and
+/// `Panic(uint256)` encodings. Returns `None` for an empty or unrecognized buffer (e.g. a custom
+/// Solidity error or a plain `revert()`).
+fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    if output.len() < 4 {
+        return None;
+    }
+    let (selector, data) = output.split_at(4);
+
+    if selector == ERROR_SELECTOR {
+        decode_abi_string(data)
+    } else if selector == PANIC_SELECTOR {
+        decode_abi_panic_code(data)
+    } else {
+        None
+    }
+}
+
+fn decode_abi_string(data: &[u8]) -> Option<String> {
+    let length = u64::from_be_bytes(data.get(56..64)?.try_into().ok()?) as usize;
+    let bytes = data.get(64..64 + length)?;
+
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+fn decode_abi_panic_code(data: &[u8]) -> Option<String> {
+    let code = U256::from_big_endian(data.get(..32)?);
+
+    Some(format!("0x{code:x}"))
+}
+
+fn transact_out_bytes(out: &rethnet_evm::TransactOut) -> &[u8] {
+    match out {
+        rethnet_evm::TransactOut::None => &[],
+        rethnet_evm::TransactOut::Call(output) => output.as_ref(),
+        rethnet_evm::TransactOut::Create(output, _) => output.as_ref(),
+    }
+}
+
 #[napi(object)]
 pub struct ExecutionResult {
     pub exit_code: u8,
@@ -182,6 +228,12 @@ pub struct ExecutionResult {
     pub gas_used: BigInt,
     pub gas_refunded: BigInt,
     pub logs: Vec<serde_json::Value>,
+    /// The ABI-decoded reason string or panic code, if the transaction reverted with one of
+    /// Solidity's built-in encodings.
+    pub revert_reason: Option<String>,
+    /// A human-readable description of why the transaction halted (ran out of gas, hit an
+    /// invalid opcode, overflowed the stack, etc.), for any non-revert failure.
+    pub halt: Option<String>,
 }
 
 impl TryFrom<rethnet_evm::ExecutionResult> for ExecutionResult {
@@ -194,12 +246,20 @@ impl TryFrom<rethnet_evm::ExecutionResult> for ExecutionResult {
             .map(serde_json::to_value)
             .collect::<serde_json::Result<Vec<serde_json::Value>>>()?;
 
+        let (revert_reason, halt) = match value.exit_reason {
+            Return::Revert => (decode_revert_reason(transact_out_bytes(&value.out)), None),
+            exit_reason if is_successful_exit(exit_reason) => (None, None),
+            halt => (None, Some(format!("{halt:?}"))),
+        };
+
         Ok(Self {
             exit_code: value.exit_reason as u8,
             output: value.out.into(),
             gas_used: BigInt::from(value.gas_used),
             gas_refunded: BigInt::from(value.gas_refunded),
             logs,
+            revert_reason,
+            halt,
         })
     }
 }
@@ -235,11 +295,28 @@ impl RethnetClient {
     pub fn new() -> Self {
         let (request_sender, request_receiver) = unbounded_channel();
 
-        tokio::spawn(Rethnet::run(request_receiver));
+        tokio::spawn(Rethnet::run(
+            LayeredDatabase::<RethnetLayer>::default(),
+            request_receiver,
+        ));
 
         Self { request_sender }
     }
 
+    /// Creates a client whose state is forked from a live JSON-RPC node, pinned to
+    /// `block_number`, instead of starting from an empty in-memory database.
+    #[napi(factory)]
+    pub fn with_fork(url: String, block_number: BigInt) -> Result<Self> {
+        let block_number = try_u256_from_bigint(block_number)?;
+
+        let (request_sender, request_receiver) = unbounded_channel();
+
+        let db = ForkedDatabase::new(ForkConfig { url, block_number });
+        tokio::spawn(Rethnet::run(db, request_receiver));
+
+        Ok(Self { request_sender })
+    }
+
     #[napi]
     pub async fn dry_run(&self, transaction: Transaction) -> Result<TransactionResult> {
         let transaction = transaction.try_into()?;
@@ -255,7 +332,7 @@ impl RethnetClient {
 
         receiver
             .await
-            .expect("Rethnet unexpectedly crashed")
+            .expect("Rethnet unexpectedly crashed")?
             .try_into()
     }
 
@@ -274,10 +351,31 @@ impl RethnetClient {
 
         receiver
             .await
-            .expect("Rethnet unexpectedly crashed")
+            .expect("Rethnet unexpectedly crashed")?
             .try_into()
     }
 
+    /// Estimates the minimum gas limit the transaction needs to succeed, by binary-searching
+    /// between the gas it actually used against the block gas limit and that same block gas
+    /// limit. Never mutates state.
+    #[napi]
+    pub async fn estimate_gas(&self, transaction: Transaction) -> Result<BigInt> {
+        let transaction = transaction.try_into()?;
+
+        let (sender, receiver) = oneshot::channel();
+
+        self.request_sender
+            .send(Request::EstimateGas {
+                transaction,
+                sender,
+            })
+            .map_err(|_| anyhow!("Failed to send request"))?;
+
+        let gas_limit = receiver.await.expect("Rethnet unexpectedly crashed")?;
+
+        Ok(BigInt::from(gas_limit))
+    }
+
     #[napi]
     pub async fn insert_account(&self, address: Buffer) -> Result<()> {
         let address = H160::from_slice(&address);
@@ -288,7 +386,7 @@ impl RethnetClient {
             .send(Request::InsertAccount { address, sender })
             .map_err(|_| anyhow!("Failed to send request"))?;
 
-        receiver.await.expect("Rethnet unexpectedly crashed");
+        receiver.await.expect("Rethnet unexpectedly crashed")?;
         Ok(())
     }
 
@@ -343,7 +441,7 @@ impl RethnetClient {
             })
             .map_err(|_| anyhow!("Failed to send request"))?;
 
-        receiver.await.expect("Rethnet unexpectedly crashed");
+        receiver.await.expect("Rethnet unexpectedly crashed")?;
         Ok(())
     }
 
@@ -362,7 +460,7 @@ impl RethnetClient {
             })
             .map_err(|_| anyhow!("Failed to send request"))?;
 
-        receiver.await.expect("Rethnet unexpectedly crashed");
+        receiver.await.expect("Rethnet unexpectedly crashed")?;
         Ok(())
     }
 
@@ -380,7 +478,7 @@ impl RethnetClient {
             })
             .map_err(|_| anyhow!("Failed to send request"))?;
 
-        receiver.await.expect("Rethnet unexpectedly crashed");
+        receiver.await.expect("Rethnet unexpectedly crashed")?;
         Ok(())
     }
 
@@ -399,7 +497,7 @@ impl RethnetClient {
             })
             .map_err(|_| anyhow!("Failed to send request"))?;
 
-        receiver.await.expect("Rethnet unexpectedly crashed");
+        receiver.await.expect("Rethnet unexpectedly crashed")?;
         Ok(())
     }
 
@@ -425,7 +523,42 @@ impl RethnetClient {
             })
             .map_err(|_| anyhow!("Failed to send request"))?;
 
-        receiver.await.expect("Rethnet unexpectedly crashed");
+        receiver.await.expect("Rethnet unexpectedly crashed")?;
+        Ok(())
+    }
+
+    /// Takes a snapshot of the current state, returning an opaque id that can later be passed to
+    /// [`RethnetClient::revert`] to restore exactly this state. Implements the `evm_snapshot` half
+    /// of Hardhat's test-isolation primitives.
+    #[napi]
+    pub async fn snapshot(&self) -> Result<BigInt> {
+        let (sender, receiver) = oneshot::channel();
+
+        self.request_sender
+            .send(Request::Snapshot { sender })
+            .map_err(|_| anyhow!("Failed to send request"))?;
+
+        let snapshot_id = receiver.await.expect("Rethnet unexpectedly crashed")?;
+
+        Ok(BigInt::from(snapshot_id))
+    }
+
+    /// Restores the state captured by [`RethnetClient::snapshot`], discarding every mutation made
+    /// since. Implements the `evm_revert` half of Hardhat's test-isolation primitives.
+    #[napi]
+    pub async fn revert(&self, snapshot_id: BigInt) -> Result<()> {
+        let snapshot_id = snapshot_id.get_u64().1;
+
+        let (sender, receiver) = oneshot::channel();
+
+        self.request_sender
+            .send(Request::Revert {
+                snapshot_id,
+                sender,
+            })
+            .map_err(|_| anyhow!("Failed to send request"))?;
+
+        receiver.await.expect("Rethnet unexpectedly crashed")?;
         Ok(())
     }
 }
@@ -437,66 +570,181 @@ enum Request {
     },
     DryRun {
         transaction: TxEnv,
-        sender: oneshot::Sender<(rethnet_evm::ExecutionResult, State)>,
+        sender: oneshot::Sender<anyhow::Result<(rethnet_evm::ExecutionResult, State)>>,
     },
     Run {
         transaction: TxEnv,
-        sender: oneshot::Sender<rethnet_evm::ExecutionResult>,
+        sender: oneshot::Sender<anyhow::Result<rethnet_evm::ExecutionResult>>,
+    },
+    EstimateGas {
+        transaction: TxEnv,
+        sender: oneshot::Sender<anyhow::Result<u64>>,
     },
     InsertAccount {
         address: H160,
-        sender: oneshot::Sender<()>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
     },
     InsertBlock {
         block_number: U256,
         block_hash: H256,
-        sender: oneshot::Sender<()>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
     },
     SetAccountBalance {
         address: H160,
         balance: U256,
-        sender: oneshot::Sender<()>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
     },
     SetAccountCode {
         address: H160,
         bytes: Bytes,
-        sender: oneshot::Sender<()>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
     },
     SetAccountNonce {
         address: H160,
         nonce: u64,
-        sender: oneshot::Sender<()>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
     },
     SetAccountStorageSlot {
         address: H160,
         index: U256,
         value: U256,
-        sender: oneshot::Sender<()>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
+    Snapshot {
+        sender: oneshot::Sender<anyhow::Result<u64>>,
+    },
+    Revert {
+        snapshot_id: u64,
+        sender: oneshot::Sender<anyhow::Result<()>>,
     },
 }
 
-struct Rethnet {
-    evm: EVM<LayeredDatabase<RethnetLayer>>,
+/// Collects the addresses and storage slots a transaction is about to touch: its `access_list`,
+/// plus its `caller` and (for a `Call`) its `to` address, which aren't required to be present in
+/// the access list but are read on every execution regardless.
+fn prefetch_targets(transaction: &TxEnv) -> (Vec<H160>, Vec<(H160, Vec<U256>)>) {
+    let mut accounts: Vec<H160> = vec![transaction.caller];
+    if let TransactTo::Call(to) = transaction.transact_to {
+        accounts.push(to);
+    }
+    accounts.extend(transaction.access_list.iter().map(|(address, _)| *address));
+
+    (accounts, transaction.access_list.clone())
+}
+
+/// Whether an EVM exit reason represents a transaction that completed without reverting or
+/// halting (e.g. running out of gas).
+fn is_successful_exit(exit_reason: Return) -> bool {
+    matches!(exit_reason, Return::Stop | Return::Return | Return::SelfDestruct)
+}
+
+struct Rethnet<DB> {
+    evm: EVM<DB>,
     request_receiver: UnboundedReceiver<Request>,
+    /// The number of snapshots currently open, i.e. the depth of the database's checkpoint stack.
+    /// Doubles as the id handed out by [`Rethnet::snapshot`], since checkpoints only ever nest.
+    snapshot_depth: u64,
 }
 
-impl Rethnet {
-    pub fn new(request_receiver: UnboundedReceiver<Request>) -> Self {
+impl<DB> Rethnet<DB>
+where
+    DB: Database<Error = anyhow::Error>
+        + rethnet_evm::DatabaseCommit
+        + DatabaseDebug<Error = anyhow::Error>
+        + Prefetch
+        + Send
+        + 'static,
+{
+    pub fn with_db(db: DB, request_receiver: UnboundedReceiver<Request>) -> Self {
         let mut evm = EVM::new();
-        evm.database(LayeredDatabase::default());
+        evm.database(db);
 
         Self {
             evm,
             request_receiver,
+            snapshot_depth: 0,
         }
     }
 
-    pub async fn run(request_receiver: UnboundedReceiver<Request>) -> anyhow::Result<()> {
-        let mut rethnet = Rethnet::new(request_receiver);
+    pub async fn run(db: DB, request_receiver: UnboundedReceiver<Request>) -> anyhow::Result<()> {
+        let mut rethnet = Rethnet::with_db(db, request_receiver);
 
         rethnet.event_loop().await
     }
 
+    /// Binary-searches the minimum `gas_limit` the transaction needs to succeed, bounded above
+    /// by the current block's gas limit. Only ever calls `transact`, never `transact_commit`, so
+    /// no search step is allowed to mutate state.
+    fn estimate_gas(&mut self, transaction: TxEnv) -> anyhow::Result<u64> {
+        let original_tx = self.evm.env.tx.clone();
+        let block_gas_limit = self.evm.env.block.gas_limit.as_u64();
+
+        self.evm.env.tx = transaction;
+        self.evm.env.tx.gas_limit = block_gas_limit;
+
+        let (result, _) = self.evm.transact();
+        if !is_successful_exit(result.exit_reason) {
+            self.evm.env.tx = original_tx;
+            return Err(anyhow!(
+                "Transaction would still fail at the block gas limit"
+            ));
+        }
+
+        let mut lo = result.gas_used.saturating_sub(1);
+        let mut hi = block_gas_limit;
+
+        // `result.gas_used` is a known-succeeding upper estimate, but the search below requires
+        // `lo` to be a known-failing lower bound; verify that explicitly rather than assuming
+        // `gas_used - 1` always fails (e.g. gas-dependent control flow can make a lower gas limit
+        // take a cheaper path and succeed anyway). If it does succeed, fall back to 0, which can
+        // never succeed, and let the search below converge from there instead of stopping short.
+        self.evm.env.tx.gas_limit = lo;
+        let (lo_result, _) = self.evm.transact();
+        if is_successful_exit(lo_result.exit_reason) {
+            hi = lo;
+            lo = 0;
+        }
+
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            self.evm.env.tx.gas_limit = mid;
+
+            let (result, _) = self.evm.transact();
+            if is_successful_exit(result.exit_reason) {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+
+        self.evm.env.tx = original_tx;
+        Ok(hi)
+    }
+
+    /// Pushes a new layer onto the database, returning its depth as an opaque handle that
+    /// [`Rethnet::revert_to_snapshot`] can later restore to.
+    fn snapshot(&mut self) -> anyhow::Result<u64> {
+        self.evm.db().unwrap().checkpoint()?;
+        self.snapshot_depth += 1;
+
+        Ok(self.snapshot_depth)
+    }
+
+    /// Discards every layer pushed since `snapshot_id` was returned by [`Rethnet::snapshot`],
+    /// restoring accounts, code, and storage to exactly their state at that point.
+    fn revert_to_snapshot(&mut self, snapshot_id: u64) -> anyhow::Result<()> {
+        if snapshot_id == 0 || snapshot_id > self.snapshot_depth {
+            return Err(anyhow!("Unknown snapshot id `{snapshot_id}`"));
+        }
+
+        while self.snapshot_depth >= snapshot_id {
+            self.evm.db().unwrap().revert()?;
+            self.snapshot_depth -= 1;
+        }
+
+        Ok(())
+    }
+
     async fn event_loop(&mut self) -> anyhow::Result<()> {
         while let Some(request) = self.request_receiver.recv().await {
             let sent_response = match request {
@@ -507,58 +755,84 @@ impl Rethnet {
                     transaction,
                     sender,
                 } => {
-                    self.evm.env.tx = transaction;
-                    sender.send(self.evm.transact()).is_ok()
+                    let (accounts, storage) = prefetch_targets(&transaction);
+                    let result = match self.evm.db().unwrap().prefetch(&accounts, &storage).await {
+                        Ok(()) => {
+                            self.evm.env.tx = transaction;
+                            Ok(self.evm.transact())
+                        }
+                        Err(error) => Err(error),
+                    };
+                    sender.send(result).is_ok()
                 }
                 Request::Run {
                     transaction,
                     sender,
                 } => {
-                    self.evm.env.tx = transaction;
-                    sender.send(self.evm.transact_commit()).is_ok()
+                    let (accounts, storage) = prefetch_targets(&transaction);
+                    let result = match self.evm.db().unwrap().prefetch(&accounts, &storage).await {
+                        Ok(()) => {
+                            self.evm.env.tx = transaction;
+                            Ok(self.evm.transact_commit())
+                        }
+                        Err(error) => Err(error),
+                    };
+                    sender.send(result).is_ok()
                 }
+                Request::EstimateGas {
+                    transaction,
+                    sender,
+                } => sender.send(self.estimate_gas(transaction)).is_ok(),
                 Request::InsertAccount { address, sender } => {
-                    self.evm
+                    let result = self
+                        .evm
                         .db()
                         .unwrap()
-                        .insert_account(&address, AccountInfo::default());
-                    sender.send(()).is_ok()
+                        .insert_account(address, AccountInfo::default());
+                    sender.send(result).is_ok()
                 }
                 Request::InsertBlock {
                     block_number,
                     block_hash,
                     sender,
                 } => {
-                    self.evm
-                        .db()
-                        .unwrap()
-                        .insert_block(block_number, block_hash);
-                    sender.send(()).is_ok()
+                    let result = self.evm.db().unwrap().insert_block(block_number, block_hash);
+                    sender.send(result).is_ok()
                 }
                 Request::SetAccountBalance {
                     address,
                     balance,
                     sender,
                 } => {
-                    self.evm.db().unwrap().account_info_mut(&address).balance = balance;
-                    sender.send(()).is_ok()
+                    let result = self.evm.db().unwrap().modify_account(
+                        address,
+                        Box::new(move |account_info| account_info.balance = balance),
+                    );
+                    sender.send(result).is_ok()
                 }
                 Request::SetAccountCode {
                     address,
                     bytes,
                     sender,
                 } => {
-                    self.evm.db().unwrap().account_info_mut(&address).code =
-                        Some(Bytecode::new_raw(bytes));
-                    sender.send(()).is_ok()
+                    let result = self.evm.db().unwrap().modify_account(
+                        address,
+                        Box::new(move |account_info| {
+                            account_info.code = Some(Bytecode::new_raw(bytes.clone()))
+                        }),
+                    );
+                    sender.send(result).is_ok()
                 }
                 Request::SetAccountNonce {
                     address,
                     nonce,
                     sender,
                 } => {
-                    self.evm.db().unwrap().account_info_mut(&address).nonce = nonce;
-                    sender.send(()).is_ok()
+                    let result = self.evm.db().unwrap().modify_account(
+                        address,
+                        Box::new(move |account_info| account_info.nonce = nonce),
+                    );
+                    sender.send(result).is_ok()
                 }
                 Request::SetAccountStorageSlot {
                     address,
@@ -566,13 +840,18 @@ impl Rethnet {
                     value,
                     sender,
                 } => {
-                    self.evm
+                    let result = self
+                        .evm
                         .db()
                         .unwrap()
-                        .set_storage_slot_at_layer(address, index, value);
-
-                    sender.send(()).is_ok()
+                        .set_account_storage_slot(address, index, value);
+                    sender.send(result).is_ok()
                 }
+                Request::Snapshot { sender } => sender.send(self.snapshot()).is_ok(),
+                Request::Revert {
+                    snapshot_id,
+                    sender,
+                } => sender.send(self.revert_to_snapshot(snapshot_id)).is_ok(),
             };
 
             if !sent_response {
@@ -581,4 +860,263 @@ impl Rethnet {
         }
         Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_layered_rethnet() -> (Rethnet<LayeredDatabase<RethnetLayer>>, UnboundedSender<Request>)
+    {
+        let (sender, receiver) = unbounded_channel();
+        let rethnet = Rethnet::with_db(LayeredDatabase::<RethnetLayer>::default(), receiver);
+        (rethnet, sender)
+    }
+
+    #[test]
+    fn forked_database_wires_into_the_actor_without_touching_the_network() {
+        let (_sender, receiver) = unbounded_channel();
+        let db = ForkedDatabase::new(ForkConfig {
+            url: "http://localhost:1".to_string(),
+            block_number: U256::from(1),
+        });
+
+        let mut rethnet = Rethnet::with_db(db, receiver);
+
+        // Taking/reverting a snapshot only touches the layer stack, never the remote node, so
+        // this must succeed even though the configured URL is unreachable.
+        let snapshot_id = rethnet.snapshot().unwrap();
+        rethnet.revert_to_snapshot(snapshot_id).unwrap();
+    }
+
+    #[test]
+    fn prefetch_targets_includes_caller_callee_and_access_list() {
+        let caller = H160::from([1; 20]);
+        let callee = H160::from([2; 20]);
+        let access_listed = H160::from([3; 20]);
+        let access_list_storage = vec![U256::from(1), U256::from(2)];
+
+        let transaction = TxEnv {
+            caller,
+            transact_to: TransactTo::Call(callee),
+            access_list: vec![(access_listed, access_list_storage.clone())],
+            ..Default::default()
+        };
+
+        let (accounts, storage) = prefetch_targets(&transaction);
+
+        assert_eq!(accounts, vec![caller, callee, access_listed]);
+        assert_eq!(storage, vec![(access_listed, access_list_storage)]);
+    }
+
+    #[test]
+    fn prefetch_targets_omits_callee_for_a_contract_creation() {
+        let caller = H160::from([1; 20]);
+
+        let transaction = TxEnv {
+            caller,
+            transact_to: TransactTo::Create(CreateScheme::Create),
+            ..Default::default()
+        };
+
+        let (accounts, storage) = prefetch_targets(&transaction);
+
+        assert_eq!(accounts, vec![caller]);
+        assert!(storage.is_empty());
+    }
+
+    #[test]
+    fn estimate_gas_finds_the_minimal_successful_gas_limit() {
+        let (mut rethnet, _sender) = new_layered_rethnet();
+
+        let caller = H160::from([1; 20]);
+        let to = H160::from([2; 20]);
+
+        rethnet
+            .evm
+            .db()
+            .unwrap()
+            .insert_account(
+                caller,
+                AccountInfo {
+                    balance: U256::from(1_000_000_000_000_000_000u64),
+                    nonce: 0,
+                    code_hash: KECCAK_EMPTY,
+                    code: None,
+                },
+            )
+            .unwrap();
+
+        rethnet.evm.env.block.gas_limit = U256::from(30_000_000);
+
+        let transaction = TxEnv {
+            caller,
+            transact_to: TransactTo::Call(to),
+            value: U256::from(1),
+            ..Default::default()
+        };
+
+        let gas_used = rethnet.estimate_gas(transaction).unwrap();
+
+        // A plain value transfer to an account with no code only ever costs the intrinsic 21,000
+        // gas, so the binary search should converge on exactly that.
+        assert_eq!(gas_used, 21_000);
+    }
+
+    #[test]
+    fn estimate_gas_errors_when_the_transaction_fails_at_the_block_gas_limit() {
+        let (mut rethnet, _sender) = new_layered_rethnet();
+
+        let caller = H160::from([1; 20]);
+        let to = H160::from([2; 20]);
+
+        rethnet.evm.env.block.gas_limit = U256::from(30_000_000);
+
+        let transaction = TxEnv {
+            caller,
+            transact_to: TransactTo::Call(to),
+            // The caller has no balance to cover this, so the transaction fails no matter the
+            // gas limit.
+            value: U256::from(1_000_000_000_000_000_000u64),
+            ..Default::default()
+        };
+
+        assert!(rethnet.estimate_gas(transaction).is_err());
+    }
+
+    /// Builds the ABI encoding of Solidity's `Error(string)` revert reason for `message`, minus
+    /// the 4-byte selector (which `decode_abi_string` never sees).
+    fn encode_abi_string(message: &str) -> Vec<u8> {
+        let mut data = vec![0u8; 32];
+        data[31] = 0x20; // offset to the string's length word
+
+        let mut length_word = vec![0u8; 32];
+        length_word[24..32].copy_from_slice(&(message.len() as u64).to_be_bytes());
+        data.extend(length_word);
+
+        let mut content = message.as_bytes().to_vec();
+        content.resize((content.len() + 31) / 32 * 32, 0);
+        data.extend(content);
+
+        data
+    }
+
+    #[test]
+    fn decode_abi_string_reads_the_length_prefixed_payload() {
+        let data = encode_abi_string("out of gas, probably");
+        assert_eq!(
+            decode_abi_string(&data),
+            Some("out of gas, probably".to_string())
+        );
+    }
+
+    #[test]
+    fn decode_abi_string_rejects_a_truncated_buffer() {
+        assert_eq!(decode_abi_string(&[0u8; 10]), None);
+    }
+
+    #[test]
+    fn decode_abi_panic_code_reads_the_32_byte_code() {
+        let mut data = vec![0u8; 32];
+        data[31] = 0x11; // arithmetic overflow/underflow
+
+        assert_eq!(decode_abi_panic_code(&data), Some("0x11".to_string()));
+    }
+
+    #[test]
+    fn decode_revert_reason_dispatches_on_the_error_selector() {
+        let mut output = ERROR_SELECTOR.to_vec();
+        output.extend(encode_abi_string("reverted"));
+
+        assert_eq!(decode_revert_reason(&output), Some("reverted".to_string()));
+    }
+
+    #[test]
+    fn decode_revert_reason_dispatches_on_the_panic_selector() {
+        let mut output = PANIC_SELECTOR.to_vec();
+        let mut code = vec![0u8; 32];
+        code[31] = 0x01; // generic assertion failure
+        output.extend(code);
+
+        assert_eq!(decode_revert_reason(&output), Some("0x1".to_string()));
+    }
+
+    #[test]
+    fn decode_revert_reason_is_none_for_an_unrecognized_or_empty_output() {
+        assert_eq!(decode_revert_reason(&[]), None);
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]), None);
+    }
+
+    #[tokio::test]
+    async fn actor_sends_request_errors_back_instead_of_panicking() {
+        let (sender, receiver) = unbounded_channel();
+        let mut rethnet = Rethnet::with_db(LayeredDatabase::<RethnetLayer>::default(), receiver);
+
+        tokio::spawn(async move { rethnet.event_loop().await });
+
+        let (response_sender, response_receiver) = oneshot::channel();
+        sender
+            .send(Request::Revert {
+                // No snapshot has been taken, so the actor must report this as an error on the
+                // response channel rather than panicking the whole event loop.
+                snapshot_id: 1,
+                sender: response_sender,
+            })
+            .unwrap();
+
+        let result = response_receiver.await.unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn snapshot_and_revert_restores_state_and_supports_nesting() {
+        let (mut rethnet, _sender) = new_layered_rethnet();
+        let address = H160::from([1; 20]);
+
+        rethnet
+            .evm
+            .db()
+            .unwrap()
+            .insert_account(
+                address,
+                AccountInfo {
+                    balance: U256::from(1),
+                    nonce: 0,
+                    code_hash: KECCAK_EMPTY,
+                    code: None,
+                },
+            )
+            .unwrap();
+
+        let snapshot_1 = rethnet.snapshot().unwrap();
+        rethnet
+            .evm
+            .db()
+            .unwrap()
+            .modify_account(address, Box::new(|account| account.balance = U256::from(2)))
+            .unwrap();
+
+        let snapshot_2 = rethnet.snapshot().unwrap();
+        rethnet
+            .evm
+            .db()
+            .unwrap()
+            .modify_account(address, Box::new(|account| account.balance = U256::from(3)))
+            .unwrap();
+
+        assert_eq!(
+            rethnet.evm.db().unwrap().basic(address).unwrap().unwrap().balance,
+            U256::from(3)
+        );
+
+        // Reverting to the first snapshot discards both nested checkpoints at once.
+        rethnet.revert_to_snapshot(snapshot_1).unwrap();
+        assert_eq!(
+            rethnet.evm.db().unwrap().basic(address).unwrap().unwrap().balance,
+            U256::from(1)
+        );
+
+        // `snapshot_2`'s id is no longer valid once it's already been reverted past.
+        assert!(rethnet.revert_to_snapshot(snapshot_2).is_err());
+    }
 }
\ No newline at end of file